@@ -0,0 +1,189 @@
+//! Typed errors produced while parsing or validating NDEF records and messages
+
+use std::error::Error;
+use std::fmt;
+
+use crate::tnf::TypeNameFormat;
+
+/// Errors that can occur while decoding NDEF records, messages, and well-known payload types
+#[derive(Debug, Clone, PartialEq)]
+pub enum NdefError {
+    /// Not enough bytes remained in the input to decode a required field
+    TooFewBytes {
+        /// Number of bytes the field being decoded required
+        needed: usize,
+        /// Number of bytes actually available
+        got: usize,
+    },
+
+    /// A record's type field contained a byte outside the allowed US-ASCII range (0-31 and 127 are invalid)
+    InvalidTypeCharacter(u8),
+
+    /// A field expected to hold UTF-8 text did not
+    InvalidUtf8,
+
+    /// A field expected to hold UTF-16 text did not
+    InvalidUtf16,
+
+    /// A record declared TNF value `0x07`, which the NFC Forum reserves without assigning it a meaning
+    ReservedTnf,
+
+    /// A chunked payload's final continuation (`cf = false`) was never reached before the byte stream ended
+    UnterminatedChunk,
+
+    /// A continuation record of a chunked payload used a TNF other than `Unchanged`
+    InvalidChunkTnf {
+        /// Index of the offending record within the message
+        record_index: usize,
+        /// TNF value the offending record declared
+        found: TypeNameFormat,
+    },
+
+    /// A continuation record of a chunked payload declared a non-zero type length
+    InvalidChunkTypeLength {
+        /// Index of the offending record within the message
+        record_index: usize,
+        /// Type length the offending record declared
+        type_length: u8,
+    },
+
+    /// A declared length field didn't match the number of bytes actually available for that field
+    PayloadLengthMismatch {
+        /// Length declared by the relevant length field
+        declared: usize,
+        /// Length actually available
+        actual: usize,
+    },
+
+    /// `mb` was missing from the first record of a message, or set on a later one
+    InvalidMessageBegin {
+        /// Index of the offending record within the message
+        record_index: usize,
+    },
+
+    /// `me` was missing from the last record of a message, or set on an earlier one
+    InvalidMessageEnd {
+        /// Index of the offending record within the message
+        record_index: usize,
+    },
+
+    /// A message contained no records
+    EmptyMessage,
+
+    /// A URI record used an identifier code in the RFU range (`0x24`-`0xFF`)
+    ReservedUriIdentifierCode(u8),
+
+    /// A text record's status byte had its reserved bit (bit 6) set
+    ReservedTextStatusBit,
+
+    /// An `NDEFRecord` was asked to convert into a well-known payload type whose type field it doesn't match
+    UnexpectedRecordType {
+        /// The record type the conversion requires
+        expected: &'static str,
+        /// The record type actually found
+        found: String,
+    },
+
+    /// Armored input could not be decoded as valid Base64
+    InvalidBase64,
+
+    /// Armored input could not be decoded as valid hexadecimal
+    InvalidHex,
+}
+
+impl fmt::Display for NdefError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NdefError::TooFewBytes { needed, got } => write!(
+                f,
+                "too few bytes to decode field: needed {}, got {}",
+                needed, got
+            ),
+            NdefError::InvalidTypeCharacter(byte) => {
+                write!(f, "invalid character code {} found in type field", byte)
+            }
+            NdefError::InvalidUtf8 => write!(f, "unable to decode field as valid UTF-8"),
+            NdefError::InvalidUtf16 => write!(f, "unable to decode field as valid UTF-16"),
+            NdefError::ReservedTnf => write!(f, "TNF value 0x07 is reserved by the NFC Forum"),
+            NdefError::UnterminatedChunk => write!(
+                f,
+                "reached end of message before a chunk's final record (cf = false)"
+            ),
+            NdefError::InvalidChunkTnf {
+                record_index,
+                found,
+            } => write!(
+                f,
+                "chunk continuation record {} must use TNF Unchanged, found {:?}",
+                record_index, found
+            ),
+            NdefError::InvalidChunkTypeLength {
+                record_index,
+                type_length,
+            } => write!(
+                f,
+                "chunk continuation record {} must have type_length 0, found {}",
+                record_index, type_length
+            ),
+            NdefError::PayloadLengthMismatch { declared, actual } => write!(
+                f,
+                "declared length {} does not match available length {}",
+                declared, actual
+            ),
+            NdefError::InvalidMessageBegin { record_index } => write!(
+                f,
+                "mb must be set on the first record only, violated at record {}",
+                record_index
+            ),
+            NdefError::InvalidMessageEnd { record_index } => write!(
+                f,
+                "me must be set on the last record only, violated at record {}",
+                record_index
+            ),
+            NdefError::EmptyMessage => write!(f, "message must contain at least one record"),
+            NdefError::ReservedUriIdentifierCode(code) => write!(
+                f,
+                "URI identifier code {:#04x} is reserved for future use",
+                code
+            ),
+            NdefError::ReservedTextStatusBit => {
+                write!(f, "text record status byte has reserved bit 6 set")
+            }
+            NdefError::UnexpectedRecordType { expected, found } => write!(
+                f,
+                "expected well-known record type '{}', found '{}'",
+                expected, found
+            ),
+            NdefError::InvalidBase64 => write!(f, "unable to decode input as valid Base64"),
+            NdefError::InvalidHex => write!(f, "unable to decode input as valid hexadecimal"),
+        }
+    }
+}
+
+impl Error for NdefError {}
+
+impl NdefError {
+    /// Stable numeric code for this error, suitable for returning across the C FFI boundary where native
+    /// callers can't match on a Rust enum
+    pub fn error_code(&self) -> i32 {
+        match self {
+            NdefError::TooFewBytes { .. } => 1,
+            NdefError::InvalidTypeCharacter(_) => 2,
+            NdefError::InvalidUtf8 => 3,
+            NdefError::InvalidUtf16 => 4,
+            NdefError::ReservedTnf => 5,
+            NdefError::UnterminatedChunk => 6,
+            NdefError::InvalidChunkTnf { .. } => 7,
+            NdefError::InvalidChunkTypeLength { .. } => 8,
+            NdefError::PayloadLengthMismatch { .. } => 9,
+            NdefError::InvalidMessageBegin { .. } => 10,
+            NdefError::InvalidMessageEnd { .. } => 11,
+            NdefError::EmptyMessage => 12,
+            NdefError::ReservedUriIdentifierCode(_) => 13,
+            NdefError::ReservedTextStatusBit => 14,
+            NdefError::UnexpectedRecordType { .. } => 15,
+            NdefError::InvalidBase64 => 16,
+            NdefError::InvalidHex => 17,
+        }
+    }
+}
@@ -55,11 +55,14 @@ use std::str::from_utf8;
 use std::{ptr, slice};
 
 // Library modules
+pub mod error;
+pub mod message;
 pub mod record_header;
 pub mod tnf;
-mod types;
+pub mod types;
 pub mod well_known_types;
 
+use error::NdefError;
 use record_header::RecordHeader;
 
 /// NDEF record struct
@@ -100,21 +103,27 @@ pub struct NDEFRecord {
     pub payload: Vec<u8>,
 }
 
-// Allows us to convert from the raw bytes collected from C (converted to u8) into a Record struct
-impl TryFrom<&[u8]> for NDEFRecord {
-    type Error = String;
-
-    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        let mut value = VecDeque::from(value.to_vec());
+impl NDEFRecord {
+    /// Parse a single record from the front of `bytes`, returning the record along with the number
+    /// of bytes consumed from the start of the slice
+    ///
+    /// This is the workhorse behind [`TryFrom<&[u8]>`](#impl-TryFrom%3C%26%5Bu8%5D%3E-for-NDEFRecord) and is
+    /// also used by [`NDEFMessage`](message/struct.NDEFMessage.html) to walk a multi-record byte stream
+    /// record-by-record without knowing each record's length ahead of time
+    pub(crate) fn parse_one(bytes: &[u8]) -> Result<(Self, usize), NdefError> {
+        let start_len = bytes.len();
+        let mut value = VecDeque::from(bytes.to_vec());
 
         if value.len() < 4 {
             // There are at least 4 required octets (fields)
-            return Err("Invalid number of octets, must have at least 4".to_string());
+            return Err(NdefError::TooFewBytes {
+                needed: 4,
+                got: value.len(),
+            });
         }
 
         // Read first byte into flags and TNF bits - first two bytes are sure to exist due to size check at start
         let header = RecordHeader::new(value.pop_front().unwrap());
-        println!("{:#?}", header);
         let type_length: u8 = value.pop_front().unwrap();
         let payload_length: u32 = match header.sr {
             true => value.pop_front().unwrap() as u32,
@@ -127,10 +136,10 @@ impl TryFrom<&[u8]> for NDEFRecord {
                     length[i] = match value.pop_front() {
                         Some(byte) => byte,
                         None => {
-                            return Err(format!(
-                                "Too few bytes to create payload length, needed 4 got {}",
-                                i
-                            ))
+                            return Err(NdefError::TooFewBytes {
+                                needed: 4,
+                                got: i,
+                            })
                         }
                     };
                 }
@@ -145,17 +154,21 @@ impl TryFrom<&[u8]> for NDEFRecord {
         if header.il {
             id_length = match value.pop_front() {
                 Some(len) => Some(len),
-                None => return Err("Missing ID length byte".to_string()),
+                None => {
+                    return Err(NdefError::TooFewBytes {
+                        needed: 1,
+                        got: 0,
+                    })
+                }
             };
         }
 
         // Check if there are enough bytes to pull out type field
         if value.len() < type_length as usize {
-            return Err(format!(
-                "Too few bytes to create ID length field: need {}, have {}",
-                type_length,
-                value.len()
-            ));
+            return Err(NdefError::TooFewBytes {
+                needed: type_length as usize,
+                got: value.len(),
+            });
         }
 
         // Checked that the bytes we require are available, now collect them
@@ -164,11 +177,9 @@ impl TryFrom<&[u8]> for NDEFRecord {
         // Create the type field from the bytes, converting them into ASCII characters after validating them
         let mut type_field = String::new();
         for byte in type_bytes.into_iter() {
-            if byte < 31 || byte == 127 {
+            if byte < 32 || byte == 127 {
                 // Invalid character, no ASCII characters [0-31] or 127
-                return Err(
-                    format!("Invalid character code {} found in type field", byte).to_string(),
-                );
+                return Err(NdefError::InvalidTypeCharacter(byte));
             }
 
             // Append valid character to type string
@@ -184,11 +195,10 @@ impl TryFrom<&[u8]> for NDEFRecord {
         if tmp_length > 0 {
             // Check if there are enough bytes to pull out id field
             if value.len() < tmp_length {
-                return Err(format!(
-                    "Too few bytes to create ID field: need {}, have {}",
-                    tmp_length,
-                    value.len()
-                ));
+                return Err(NdefError::TooFewBytes {
+                    needed: tmp_length,
+                    got: value.len(),
+                });
             }
 
             // Checked that the bytes we require are available, now collect them
@@ -197,35 +207,175 @@ impl TryFrom<&[u8]> for NDEFRecord {
             // Convert ID from bytes to UTF-8 characters
             id_field = match from_utf8(&id_bytes) {
                 Ok(id_val) => Some(id_val.to_string()),
-                Err(_) => return Err("Unable to convert ID bytes to valid UTF-8".to_string()),
+                Err(_) => return Err(NdefError::InvalidUtf8),
             };
         }
 
         // Collect remaining data as payload after validating length
         if value.len() < payload_length as usize {
-            return Err(format!(
-                "Too few bytes to create payload field: need {}, have {}",
-                payload_length,
-                value.len()
-            ));
+            return Err(NdefError::TooFewBytes {
+                needed: payload_length as usize,
+                got: value.len(),
+            });
         }
         let payload: Vec<u8> = Vec::from_iter(value.drain(0..payload_length as usize).into_iter());
 
+        // Number of bytes consumed from the front of the slice to build this record
+        let consumed = start_len - value.len();
+
         // Succesfully built Record object from u8 slice
-        Ok(NDEFRecord {
-            header: header,
-            type_length: type_length,
-            payload_length: payload_length,
-            id_length: id_length,
-            record_type: type_field,
-            id_field: id_field,
-            payload: payload,
-        })
+        Ok((
+            NDEFRecord {
+                header: header,
+                type_length: type_length,
+                payload_length: payload_length,
+                id_length: id_length,
+                record_type: type_field,
+                id_field: id_field,
+                payload: payload,
+            },
+            consumed,
+        ))
+    }
+}
+
+// Allows us to convert from the raw bytes collected from C (converted to u8) into a Record struct
+impl TryFrom<&[u8]> for NDEFRecord {
+    type Error = NdefError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        NDEFRecord::parse_one(value).map(|(record, _consumed)| record)
+    }
+}
+
+impl NDEFRecord {
+    /// Serialize this record back into its raw NDEF byte representation
+    ///
+    /// The header's `sr` and `il` flags, and the type/payload length fields, are derived fresh from the
+    /// record's actual data rather than trusted from whatever was stored on the struct, so a record built by
+    /// hand or mutated after parsing still serializes correctly
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_flags(self.header.mb, self.header.me)
+    }
+
+    /// Same as [`to_bytes`](#method.to_bytes), but with the `mb`/`me` header bits overridden
+    ///
+    /// Used by [`NDEFMessage::to_bytes`](message/struct.NDEFMessage.html#method.to_bytes) to force `mb`/`me`
+    /// onto the first/last record of a message without needing to clone and patch the record first
+    ///
+    /// Debug builds assert that [`type_length`](#structfield.type_length) and
+    /// [`payload_length`](#structfield.payload_length) agree with the actual length of
+    /// [`record_type`](#structfield.record_type)/[`payload`](#structfield.payload), since the bytes written
+    /// here are always derived fresh from the data - a record hand-built or mutated with an inconsistent
+    /// declared length would otherwise round-trip silently to different bytes
+    pub(crate) fn to_bytes_with_flags(&self, mb: bool, me: bool) -> Vec<u8> {
+        let type_bytes = self.record_type.as_bytes();
+        let id_bytes = self.id_field.as_ref().map(|id| id.as_bytes());
+
+        debug_assert_eq!(
+            self.type_length as usize,
+            type_bytes.len(),
+            "type_length does not match record_type's actual length"
+        );
+        debug_assert_eq!(
+            self.payload_length as usize,
+            self.payload.len(),
+            "payload_length does not match payload's actual length"
+        );
+
+        // Derive sr/il from the data itself rather than trusting stale flags
+        let sr = self.payload.len() <= u8::MAX as usize;
+        let il = id_bytes.is_some();
+
+        let header = RecordHeader {
+            mb: mb,
+            me: me,
+            cf: self.header.cf,
+            sr: sr,
+            il: il,
+            tnf: self.header.tnf.clone(),
+        };
+
+        let mut bytes: Vec<u8> = vec![header.into(), type_bytes.len() as u8];
+
+        if sr {
+            bytes.push(self.payload.len() as u8);
+        } else {
+            bytes.extend_from_slice(&(self.payload.len() as u32).to_be_bytes());
+        }
+
+        if let Some(id_bytes) = id_bytes {
+            bytes.push(id_bytes.len() as u8);
+        }
+
+        bytes.extend_from_slice(type_bytes);
+
+        if let Some(id_bytes) = id_bytes {
+            bytes.extend_from_slice(id_bytes);
+        }
+
+        bytes.extend_from_slice(&self.payload);
+
+        bytes
     }
 }
 
 #[no_mangle]
-pub extern "C" fn ndef_recordFromBytes(bytes: *const uint8_t, len: size_t) -> *mut NDEFRecord {
+pub extern "C" fn ndef_recordToBytes(
+    record: *const NDEFRecord,
+    out_len: *mut size_t,
+) -> *mut uint8_t {
+    let record: &NDEFRecord = unsafe {
+        // Confirm that the record pointer passed is not null to start
+        assert!(!record.is_null());
+
+        &*record
+    };
+
+    let mut bytes = record.to_bytes().into_boxed_slice();
+    let ptr = bytes.as_mut_ptr();
+
+    unsafe {
+        if !out_len.is_null() {
+            *out_len = bytes.len();
+        }
+    }
+
+    // Hand ownership of the buffer to the caller; they're expected to free it via ndef_freeBytes
+    std::mem::forget(bytes);
+    ptr
+}
+
+#[no_mangle]
+pub extern "C" fn ndef_freeBytes(bytes: *mut uint8_t, len: size_t) {
+    if bytes.is_null() {
+        return;
+    }
+
+    unsafe {
+        // Reconstruct and drop the boxed slice handed out by ndef_recordToBytes
+        drop(Box::from_raw(slice::from_raw_parts_mut(bytes, len)));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ndef_freeRecord(record: *mut NDEFRecord) {
+    if record.is_null() {
+        return;
+    }
+
+    unsafe {
+        // Reconstruct and drop the box handed out by ndef_recordFromBytes
+        drop(Box::from_raw(record));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn ndef_recordFromBytes(
+    bytes: *const uint8_t,
+    len: size_t,
+    out_error: *mut i32,
+) -> *mut NDEFRecord {
     let record_bytes: &[u8] = unsafe {
         // Confirm that the bytes pointer passed is not null to start
         assert!(!bytes.is_null());
@@ -236,9 +386,22 @@ pub extern "C" fn ndef_recordFromBytes(bytes: *const uint8_t, len: size_t) -> *m
 
     // Attempt to create Record object from converted slice of u8
     match NDEFRecord::try_from(record_bytes) {
-        Ok(record) => Box::into_raw(Box::new(record)),
+        Ok(record) => {
+            unsafe {
+                if !out_error.is_null() {
+                    *out_error = 0;
+                }
+            }
+
+            Box::into_raw(Box::new(record))
+        }
         Err(err) => {
-            eprintln!("{}", err);
+            unsafe {
+                if !out_error.is_null() {
+                    *out_error = err.error_code();
+                }
+            }
+
             ptr::null_mut()
         }
     }
@@ -270,8 +433,8 @@ mod tests {
         let well_known_type: u8 = 0x54;
 
         // Text encoding information
-        // - UTF-8 (1b), RFU (always 0b), IANA language code "en-US" length = 5 (0b00101)
-        let text_flag: u8 = 0x85;
+        // - UTF-8 (0b), RFU (always 0b), IANA language code "en-US" length = 5 (0b000101)
+        let text_flag: u8 = 0x05;
 
         // ISO/IANA language code "en-US" encoded in US-ASCII
         let lang_code_bytes = vec![0x65, 0x6e, 0x2d, 0x55, 0x53];
@@ -304,32 +467,12 @@ mod tests {
 
         let record = match NDEFRecord::try_from(test_bytes.as_slice()) {
             Ok(record) => record,
-            Err(err) => panic!(err),
+            Err(err) => panic!("{}", err),
         };
 
-        // Get encoding and country code from payload
-        let mut text_payload: String;
-        if ((record.payload[0] >> 7) & 0x01) != 0 {
-            // Decoding UTF-8
-            // Ignore language code length and language code, last 5 bits are the ISO/IANA language code bytes length
-            let lang_code_len = record.payload[0] & 0x17;
+        use super::well_known_types::text::{TextEncoding, TextRecord};
 
-            // Ignore UTF-x/RFU/IANA code length byte and then ISO/IANA language code bytes
-            let num_ignore_bytes = (1 + lang_code_len) as usize;
-
-            // Extract text payload from UTF-8 bytes
-            text_payload = match String::from_utf8(record.payload[num_ignore_bytes..].to_vec()) {
-                Ok(txt) => txt,
-                Err(err) => panic!("{}", err),
-            };
-        } else {
-            // Decoding UTF-16 is not supported currently
-            panic!("Oh frick, we can't deal with UTF-16");
-            // text_payload = match String::from_utf16(record.payload.into()) {
-            //     Ok(txt) => txt,
-            //     Err(err) => panic!("{}", err),
-            // };
-        }
+        let text_record = TextRecord::try_from(&record).expect("valid text record payload");
 
         assert_eq!(record.id_length, None);
         assert_eq!(record.id_field, None);
@@ -337,6 +480,25 @@ mod tests {
         assert_eq!(record.payload_length, 19);
         assert_eq!(record.record_type, "T");
         assert_eq!(record.type_length, 1);
-        assert_eq!(text_payload, "Hello, World!");
+        assert_eq!(text_record.language_code, "en-US");
+        assert_eq!(text_record.encoding, TextEncoding::Utf8);
+        assert_eq!(text_record.text, "Hello, World!");
+    }
+
+    #[test]
+    fn ndef_record_to_bytes_round_trips() {
+        use super::NDEFRecord;
+        use std::convert::TryFrom;
+
+        let test_bytes: Vec<u8> = vec![
+            0xd1, // mb, me, sr, TNF=WellKnown
+            0x01, // type length
+            0x02, // payload length
+            0x55, // "U"
+            0x00, 0x01, // payload
+        ];
+
+        let record = NDEFRecord::try_from(test_bytes.as_slice()).unwrap();
+        assert_eq!(record.to_bytes(), test_bytes);
     }
 }
@@ -0,0 +1,459 @@
+//! # NDEF Message Handling
+//!
+//! An NDEF message is an ordered sequence of one or more [`NDEFRecord`](../struct.NDEFRecord.html)s. The header of
+//! each record carries the [`mb`](../record_header/struct.RecordHeader.html#structfield.mb),
+//! [`me`](../record_header/struct.RecordHeader.html#structfield.me), and
+//! [`cf`](../record_header/struct.RecordHeader.html#structfield.cf) flags that describe where a record sits within
+//! the message and whether its payload is split across multiple records ("chunked"). This module walks a raw byte
+//! stream record-by-record, enforces those message-level invariants, and reassembles chunked payloads back into a
+//! single logical record.
+
+use std::convert::TryFrom;
+
+use crate::error::NdefError;
+use crate::tnf::TypeNameFormat;
+use crate::NDEFRecord;
+
+/// An NDEF message - an ordered, non-empty sequence of [`NDEFRecord`](../struct.NDEFRecord.html)s
+///
+/// Chunked records (where a record sets [`cf`](../record_header/struct.RecordHeader.html#structfield.cf) to split
+/// its payload across several records on the wire) are reassembled into a single logical record while parsing, so
+/// each entry in [`records`](#structfield.records) always represents one complete payload
+#[derive(Debug, PartialEq)]
+pub struct NDEFMessage {
+    /// The records that make up this message, with any chunked payloads already reassembled
+    pub records: Vec<NDEFRecord>,
+}
+
+impl TryFrom<&[u8]> for NDEFMessage {
+    type Error = NdefError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let mut records: Vec<NDEFRecord> = Vec::new();
+        let mut offset: usize = 0;
+        let mut record_index: usize = 0;
+
+        // Set while a chunk's continuation records are being folded into the record that started it
+        let mut chunk_in_progress = false;
+
+        while offset < value.len() {
+            let (mut record, consumed) = NDEFRecord::parse_one(&value[offset..])?;
+            offset += consumed;
+
+            if record_index == 0 {
+                if !record.header.mb {
+                    return Err(NdefError::InvalidMessageBegin { record_index });
+                }
+            } else if record.header.mb {
+                return Err(NdefError::InvalidMessageBegin { record_index });
+            }
+
+            if chunk_in_progress {
+                if record.header.tnf != TypeNameFormat::Unchanged {
+                    return Err(NdefError::InvalidChunkTnf {
+                        record_index,
+                        found: record.header.tnf.clone(),
+                    });
+                }
+
+                if record.type_length != 0 {
+                    return Err(NdefError::InvalidChunkTypeLength {
+                        record_index,
+                        type_length: record.type_length,
+                    });
+                }
+
+                // Fold the continuation's payload onto the record that started the chunk, keeping its
+                // original TNF and type, and take on the continuation's me/cf for message-level validation
+                let current_index = records.len() - 1;
+                let current = records
+                    .last_mut()
+                    .expect("chunk_in_progress implies a record has already been pushed");
+
+                // An earlier record of this chunk set me, yet a continuation record follows it
+                if current.header.me {
+                    return Err(NdefError::InvalidMessageEnd {
+                        record_index: current_index,
+                    });
+                }
+
+                current.payload.append(&mut record.payload);
+                current.payload_length += record.payload_length;
+                current.header.me = record.header.me;
+                current.header.cf = record.header.cf;
+
+                if !record.header.cf {
+                    chunk_in_progress = false;
+                }
+            } else {
+                if record.header.cf {
+                    chunk_in_progress = true;
+                }
+
+                records.push(record);
+            }
+
+            record_index += 1;
+        }
+
+        if records.is_empty() {
+            return Err(NdefError::EmptyMessage);
+        }
+
+        if chunk_in_progress {
+            return Err(NdefError::UnterminatedChunk);
+        }
+
+        let last_index = records.len() - 1;
+        for (index, record) in records.iter().enumerate() {
+            if index == last_index {
+                if !record.header.me {
+                    return Err(NdefError::InvalidMessageEnd { record_index: index });
+                }
+            } else if record.header.me {
+                return Err(NdefError::InvalidMessageEnd { record_index: index });
+            }
+        }
+
+        Ok(NDEFMessage { records })
+    }
+}
+
+impl NDEFMessage {
+    /// Serialize this message back into the raw NDEF byte stream, concatenating each record's bytes in order
+    ///
+    /// `mb` is forced onto the first record and `me` onto the last as they're serialized, so callers don't need
+    /// to keep those flags in sync by hand after building or mutating [`records`](#structfield.records)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let last_index = self.records.len().saturating_sub(1);
+
+        for (index, record) in self.records.iter().enumerate() {
+            bytes.extend(record.to_bytes_with_flags(index == 0, index == last_index));
+        }
+
+        bytes
+    }
+
+    /// Armor this message as Base64 text, wrapped at [`ARMOR_LINE_WIDTH`](constant.ARMOR_LINE_WIDTH.html)
+    /// characters per line so the output stays diff-friendly
+    pub fn to_base64(&self) -> String {
+        wrap_armor_lines(&base64_encode(&self.to_bytes()))
+    }
+
+    /// Parse a message out of Base64 armor produced by [`to_base64`](#method.to_base64), tolerating
+    /// arbitrary internal whitespace/newlines
+    pub fn from_base64(armored: &str) -> Result<Self, NdefError> {
+        let bytes = base64_decode(armored)?;
+        NDEFMessage::try_from(bytes.as_slice())
+    }
+
+    /// Armor this message as lowercase hex text, wrapped at
+    /// [`ARMOR_LINE_WIDTH`](constant.ARMOR_LINE_WIDTH.html) characters per line so the output stays
+    /// diff-friendly
+    pub fn to_hex(&self) -> String {
+        wrap_armor_lines(&hex_encode(&self.to_bytes()))
+    }
+
+    /// Parse a message out of hex armor produced by [`to_hex`](#method.to_hex), tolerating arbitrary
+    /// internal whitespace/newlines
+    pub fn from_hex(armored: &str) -> Result<Self, NdefError> {
+        let bytes = hex_decode(armored)?;
+        NDEFMessage::try_from(bytes.as_slice())
+    }
+}
+
+/// Line width used when armoring a message as Base64 or hex text, chosen to keep armored output diff-friendly
+const ARMOR_LINE_WIDTH: usize = 64;
+
+/// Break `text` into lines of [`ARMOR_LINE_WIDTH`](constant.ARMOR_LINE_WIDTH.html) characters, each terminated
+/// with a newline
+fn wrap_armor_lines(text: &str) -> String {
+    let mut wrapped = String::with_capacity(text.len() + text.len() / ARMOR_LINE_WIDTH + 1);
+
+    for line in text.as_bytes().chunks(ARMOR_LINE_WIDTH) {
+        wrapped.push_str(std::str::from_utf8(line).expect("armor alphabets are ASCII"));
+        wrapped.push('\n');
+    }
+
+    wrapped
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for group in bytes.chunks(3) {
+        let b0 = group[0];
+        let b1 = *group.get(1).unwrap_or(&0);
+        let b2 = *group.get(2).unwrap_or(&0);
+        let combined = ((b0 as u32) << 16) | ((b1 as u32) << 8) | (b2 as u32);
+
+        encoded.push(BASE64_ALPHABET[((combined >> 18) & 0x3f) as usize] as char);
+        encoded.push(BASE64_ALPHABET[((combined >> 12) & 0x3f) as usize] as char);
+        encoded.push(if group.len() > 1 {
+            BASE64_ALPHABET[((combined >> 6) & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+        encoded.push(if group.len() > 2 {
+            BASE64_ALPHABET[(combined & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+
+    encoded
+}
+
+fn base64_decode(armored: &str) -> Result<Vec<u8>, NdefError> {
+    let cleaned: Vec<u8> = armored
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    if cleaned.is_empty() || !cleaned.len().is_multiple_of(4) {
+        return Err(NdefError::InvalidBase64);
+    }
+
+    fn value_of(byte: u8) -> Result<u8, NdefError> {
+        match byte {
+            b'A'..=b'Z' => Ok(byte - b'A'),
+            b'a'..=b'z' => Ok(byte - b'a' + 26),
+            b'0'..=b'9' => Ok(byte - b'0' + 52),
+            b'+' => Ok(62),
+            b'/' => Ok(63),
+            _ => Err(NdefError::InvalidBase64),
+        }
+    }
+
+    let mut decoded = Vec::with_capacity(cleaned.len() / 4 * 3);
+    let group_count = cleaned.len() / 4;
+
+    for (index, group) in cleaned.chunks(4).enumerate() {
+        let padding = group.iter().filter(|&&byte| byte == b'=').count();
+        let is_final_group = index == group_count - 1;
+
+        // Padding, if any, may only appear at the end of the final group
+        if padding > 2
+            || (!is_final_group && padding > 0)
+            || group[..group.len() - padding].iter().any(|&byte| byte == b'=')
+        {
+            return Err(NdefError::InvalidBase64);
+        }
+
+        let mut values = [0u8; 4];
+        for (index, &byte) in group.iter().enumerate() {
+            values[index] = if byte == b'=' { 0 } else { value_of(byte)? };
+        }
+
+        let combined = ((values[0] as u32) << 18)
+            | ((values[1] as u32) << 12)
+            | ((values[2] as u32) << 6)
+            | (values[3] as u32);
+
+        decoded.push((combined >> 16) as u8);
+        if padding < 2 {
+            decoded.push((combined >> 8) as u8);
+        }
+        if padding < 1 {
+            decoded.push(combined as u8);
+        }
+    }
+
+    Ok(decoded)
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() * 2);
+
+    for byte in bytes {
+        encoded.push_str(&format!("{:02x}", byte));
+    }
+
+    encoded
+}
+
+fn hex_decode(armored: &str) -> Result<Vec<u8>, NdefError> {
+    let cleaned: Vec<u8> = armored
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+
+    if cleaned.is_empty() || !cleaned.len().is_multiple_of(2) {
+        return Err(NdefError::InvalidHex);
+    }
+
+    let mut decoded = Vec::with_capacity(cleaned.len() / 2);
+
+    for pair in cleaned.chunks(2) {
+        let high = (pair[0] as char).to_digit(16).ok_or(NdefError::InvalidHex)?;
+        let low = (pair[1] as char).to_digit(16).ok_or(NdefError::InvalidHex)?;
+        decoded.push(((high << 4) | low) as u8);
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NDEFMessage;
+    use crate::tnf::TypeNameFormat;
+    use std::convert::TryFrom;
+
+    // Header byte helper: mb, me, sr always set, cf and il never set, WellKnown TNF
+    const TEXT_HEADER: u8 = 0xd1;
+
+    #[test]
+    fn single_record_message() {
+        // mb=1, me=1, cf=0, sr=1, il=0, TNF=WellKnown(1)
+        let test_bytes: Vec<u8> = vec![
+            TEXT_HEADER,
+            0x01, // type length
+            0x02, // payload length
+            0x54, // "T"
+            0x01, 0x02, // payload
+        ];
+
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+        assert_eq!(message.records.len(), 1);
+        assert_eq!(message.records[0].payload, vec![0x01, 0x02]);
+    }
+
+    #[test]
+    fn chunked_message_reassembles_payload() {
+        // First chunk: mb=1, me=0, cf=1, sr=1, il=0, TNF=WellKnown(1) -> 0xb1
+        let first = vec![0xb1, 0x01, 0x01, 0x54, 0xaa];
+
+        // Middle chunk: mb=0, me=0, cf=1, sr=1, il=0, TNF=Unchanged(6) -> 0x36
+        let middle = vec![0x36, 0x00, 0x01, 0xbb];
+
+        // Final chunk: mb=0, me=1, cf=0, sr=1, il=0, TNF=Unchanged(6) -> 0x56
+        let last = vec![0x56, 0x00, 0x01, 0xcc];
+
+        let mut test_bytes = Vec::new();
+        test_bytes.extend(first);
+        test_bytes.extend(middle);
+        test_bytes.extend(last);
+
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+        assert_eq!(message.records.len(), 1);
+
+        let record = &message.records[0];
+        assert_eq!(record.header.tnf, TypeNameFormat::WellKnown);
+        assert_eq!(record.record_type, "T");
+        assert_eq!(record.payload, vec![0xaa, 0xbb, 0xcc]);
+        assert_eq!(record.payload_length, 3);
+        assert!(record.header.mb);
+        assert!(record.header.me);
+        assert!(!record.header.cf);
+    }
+
+    #[test]
+    fn unterminated_chunk_is_an_error() {
+        // First chunk: mb=1, me=1, cf=1, sr=1, il=0, TNF=WellKnown(1) -> 0xd1 with cf set -> 0xf1
+        let only_chunk = vec![0xf1, 0x01, 0x01, 0x54, 0xaa];
+
+        let result = NDEFMessage::try_from(only_chunk.as_slice());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn to_bytes_round_trips_and_forces_mb_me() {
+        // Parse a valid message, then clear mb/me in memory - to_bytes() should still put them back
+        // onto the first/last record since they're derived from each record's position, not stored state
+        let first = vec![0x91, 0x01, 0x01, 0x54, 0xaa]; // mb=1, me=0, sr=1, TNF=WellKnown
+        let last = vec![0x51, 0x01, 0x01, 0x55, 0xbb]; // mb=0, me=1, sr=1, TNF=WellKnown
+
+        let mut test_bytes = Vec::new();
+        test_bytes.extend(first);
+        test_bytes.extend(last);
+
+        let mut message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+        message.records[0].header.mb = false;
+        message.records[1].header.me = false;
+
+        let expected: Vec<u8> = vec![
+            0x91, 0x01, 0x01, 0x54, 0xaa, // mb forced back on
+            0x51, 0x01, 0x01, 0x55, 0xbb, // me forced back on
+        ];
+        assert_eq!(message.to_bytes(), expected);
+    }
+
+    #[test]
+    fn base64_round_trips_message_bytes() {
+        let test_bytes: Vec<u8> = vec![TEXT_HEADER, 0x01, 0x01, 0x54, 0xaa];
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+
+        let armored = message.to_base64();
+        let decoded = NDEFMessage::from_base64(&armored).unwrap();
+
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn base64_decode_tolerates_internal_whitespace() {
+        let test_bytes: Vec<u8> = vec![TEXT_HEADER, 0x01, 0x01, 0x54, 0xaa];
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+
+        let armored = message.to_base64();
+        let with_whitespace = format!(" {}\n\t{}", &armored[..2], &armored[2..]);
+        let decoded = NDEFMessage::from_base64(&with_whitespace).unwrap();
+
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn hex_round_trips_message_bytes() {
+        let test_bytes: Vec<u8> = vec![TEXT_HEADER, 0x01, 0x01, 0x54, 0xaa];
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+
+        let armored = message.to_hex();
+        let decoded = NDEFMessage::from_hex(&armored).unwrap();
+
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn hex_wraps_long_output_at_line_width() {
+        let mut payload = vec![0xab; 100];
+        let mut test_bytes: Vec<u8> = vec![TEXT_HEADER, 0x01, payload.len() as u8, 0x54];
+        test_bytes.append(&mut payload);
+
+        let message = NDEFMessage::try_from(test_bytes.as_slice()).unwrap();
+        let armored = message.to_hex();
+
+        let longest_line = armored.lines().map(|line| line.len()).max().unwrap();
+        assert!(longest_line <= 64);
+        assert_eq!(NDEFMessage::from_hex(&armored).unwrap(), message);
+    }
+
+    #[test]
+    fn from_base64_rejects_malformed_input() {
+        assert!(NDEFMessage::from_base64("not valid base64!!").is_err());
+    }
+
+    #[test]
+    fn from_base64_rejects_padding_before_the_final_group() {
+        // "AB==" is valid padding, but only in the last group - here it's followed by another group
+        assert!(NDEFMessage::from_base64("AB==AAAA").is_err());
+    }
+
+    #[test]
+    fn from_hex_rejects_malformed_input() {
+        assert!(NDEFMessage::from_hex("zz").is_err());
+    }
+
+    #[test]
+    fn mb_on_non_first_record_is_an_error() {
+        let mut test_bytes: Vec<u8> = vec![TEXT_HEADER, 0x01, 0x01, 0x54, 0xaa];
+
+        // Second record also has mb set, which is invalid
+        test_bytes.extend(vec![TEXT_HEADER, 0x01, 0x01, 0x55, 0xbb]);
+
+        let result = NDEFMessage::try_from(test_bytes.as_slice());
+        assert!(result.is_err());
+    }
+}
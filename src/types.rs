@@ -1,9 +1,19 @@
 //! Various payload types to handle the parsing and validation of each data type's payload
 
-use std::convert::{TryFrom, Into};
+use std::convert::TryFrom;
 
-pub trait Payload<T> 
-where T: TryFrom<Vec<u8>> + Into<Vec<u8>> {
+pub trait Payload<T>
+where T: TryFrom<Vec<u8>> + WritableRecord {
     /// Static creation method that converts the payload bytes to the specific struct
     fn new(bytes: Vec<u8>) -> Self;
+}
+
+/// Write side of [`Payload`](trait.Payload.html): serializes a payload type back into the raw bytes that
+/// belong in an NDEF record's payload field
+///
+/// A type can implement `WritableRecord` without implementing `Payload`; the reverse does not hold, since
+/// `Payload<T>` requires `T` to implement both `TryFrom<Vec<u8>>` and `WritableRecord`
+pub trait WritableRecord {
+    /// Serialize this payload back into the raw bytes that belong in an NDEF record's payload field
+    fn to_bytes(&self) -> Vec<u8>;
 }
\ No newline at end of file
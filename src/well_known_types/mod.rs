@@ -0,0 +1,8 @@
+//! Well-Known (NFC Forum RTD) payload types
+//!
+//! These correspond to the `TNF::WellKnown` [Type Name Format](../tnf/enum.TypeNameFormat.html#variant.WellKnown)
+//! and are identified by the single-character [`record_type`](../struct.NDEFRecord.html#structfield.record_type)
+//! values assigned by the NFC Forum Record Type Definition specifications.
+
+pub mod text;
+pub mod uri;
@@ -1,14 +1,213 @@
 use std::convert::TryFrom;
 
-/// Well-Known Record - Text - [TNF Record Type `0x01`](enum.TypeNameFormat.html#variant.WellKnown)
-/// 
-/// Well Known Type is "T" ([Type](struct.Record.html#structfield.record_type) field will be `0x54`)
-pub struct TextRecord { }
+use crate::error::NdefError;
+use crate::types::WritableRecord;
+use crate::NDEFRecord;
+
+/// Text encoding used for an RTD Text record's payload, selected by bit 7 of the status byte
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextEncoding {
+    /// Text is encoded as UTF-8
+    Utf8,
+
+    /// Text is encoded as UTF-16, optionally preceded by a byte-order-mark
+    Utf16,
+}
+
+/// Well-Known Record - Text - [TNF Record Type `0x01`](../../tnf/enum.TypeNameFormat.html#variant.WellKnown)
+///
+/// Well Known Type is "T" ([Type](../../struct.NDEFRecord.html#structfield.record_type) field will be `0x54`)
+#[derive(Debug, PartialEq)]
+pub struct TextRecord {
+    /// ISO/IANA language code describing the language of [`text`](#structfield.text), e.g. `"en-US"`
+    pub language_code: String,
+
+    /// Encoding [`text`](#structfield.text) was stored in
+    pub encoding: TextEncoding,
+
+    /// The decoded text content
+    pub text: String,
+}
 
 impl TryFrom<Vec<u8>> for TextRecord {
-    type Error = String;
+    type Error = NdefError;
+
+    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(NdefError::TooFewBytes { needed: 1, got: 0 });
+        }
+
+        let status_byte = value[0];
+
+        // Bit 6 is RFU and must always be 0
+        if (status_byte & 0x40) != 0 {
+            return Err(NdefError::ReservedTextStatusBit);
+        }
+
+        let encoding = if (status_byte & 0x80) != 0 {
+            TextEncoding::Utf16
+        } else {
+            TextEncoding::Utf8
+        };
+
+        // Bits 5-0 give the length, in bytes, of the IANA language code that follows
+        let lang_code_len = (status_byte & 0x3f) as usize;
+
+        if value.len() < 1 + lang_code_len {
+            return Err(NdefError::TooFewBytes {
+                needed: 1 + lang_code_len,
+                got: value.len(),
+            });
+        }
+
+        let language_code = match std::str::from_utf8(&value[1..1 + lang_code_len]) {
+            Ok(code) => code.to_string(),
+            Err(_) => return Err(NdefError::InvalidUtf8),
+        };
+
+        let text_bytes = &value[1 + lang_code_len..];
+
+        let text = match encoding {
+            TextEncoding::Utf8 => match std::str::from_utf8(text_bytes) {
+                Ok(text) => text.to_string(),
+                Err(_) => return Err(NdefError::InvalidUtf8),
+            },
+            TextEncoding::Utf16 => decode_utf16_text(text_bytes)?,
+        };
+
+        Ok(TextRecord {
+            language_code,
+            encoding,
+            text,
+        })
+    }
+}
+
+/// Decode a UTF-16 text field, honoring a leading byte-order-mark to pick the byte order and
+/// defaulting to big-endian when no BOM is present
+fn decode_utf16_text(bytes: &[u8]) -> Result<String, NdefError> {
+    let (bytes, big_endian) = match bytes {
+        [0xfe, 0xff, rest @ ..] => (rest, true),
+        [0xff, 0xfe, rest @ ..] => (rest, false),
+        rest => (rest, true),
+    };
+
+    if !bytes.len().is_multiple_of(2) {
+        return Err(NdefError::InvalidUtf16);
+    }
+
+    let code_units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|chunk| {
+            if big_endian {
+                u16::from_be_bytes([chunk[0], chunk[1]])
+            } else {
+                u16::from_le_bytes([chunk[0], chunk[1]])
+            }
+        })
+        .collect();
+
+    match String::from_utf16(&code_units) {
+        Ok(text) => Ok(text),
+        Err(_) => Err(NdefError::InvalidUtf16),
+    }
+}
+
+impl WritableRecord for TextRecord {
+    fn to_bytes(&self) -> Vec<u8> {
+        let lang_bytes = self.language_code.as_bytes();
+
+        assert!(
+            lang_bytes.len() <= 0x3f,
+            "language code must be 63 bytes or fewer, got {}",
+            lang_bytes.len()
+        );
+
+        let mut status_byte = lang_bytes.len() as u8;
+        if self.encoding == TextEncoding::Utf16 {
+            status_byte |= 0x80;
+        }
+
+        let mut bytes = vec![status_byte];
+        bytes.extend_from_slice(lang_bytes);
+
+        match self.encoding {
+            TextEncoding::Utf8 => bytes.extend_from_slice(self.text.as_bytes()),
+            TextEncoding::Utf16 => {
+                for unit in self.text.encode_utf16() {
+                    bytes.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+        }
+
+        bytes
+    }
+}
+
+impl TryFrom<&NDEFRecord> for TextRecord {
+    type Error = NdefError;
+
+    fn try_from(record: &NDEFRecord) -> Result<Self, Self::Error> {
+        if record.record_type != "T" {
+            return Err(NdefError::UnexpectedRecordType {
+                expected: "T",
+                found: record.record_type.clone(),
+            });
+        }
+
+        TextRecord::try_from(record.payload.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TextEncoding, TextRecord};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn decodes_utf8_text() {
+        // UTF-8 (0b), RFU (0b), "en-US" length = 5 (0b000101)
+        let mut payload = vec![0x05];
+        payload.extend_from_slice(b"en-US");
+        payload.extend_from_slice(b"Hello, World!");
+
+        let record = TextRecord::try_from(payload).unwrap();
+        assert_eq!(record.language_code, "en-US");
+        assert_eq!(record.encoding, TextEncoding::Utf8);
+        assert_eq!(record.text, "Hello, World!");
+    }
+
+    #[test]
+    fn decodes_utf16_text_with_bom() {
+        // UTF-16 (1b), RFU (0b), "en" length = 2 (0b000010)
+        let mut payload = vec![0x82];
+        payload.extend_from_slice(b"en");
+        payload.extend_from_slice(&[0xfe, 0xff]); // big-endian BOM
+        for unit in "Hi!".encode_utf16() {
+            payload.extend_from_slice(&unit.to_be_bytes());
+        }
+
+        let record = TextRecord::try_from(payload).unwrap();
+        assert_eq!(record.language_code, "en");
+        assert_eq!(record.encoding, TextEncoding::Utf16);
+        assert_eq!(record.text, "Hi!");
+    }
+
+    #[test]
+    fn round_trips_utf8_through_to_bytes() {
+        use crate::types::WritableRecord;
+
+        let mut payload = vec![0x05];
+        payload.extend_from_slice(b"en-US");
+        payload.extend_from_slice(b"Hello, World!");
+
+        let record = TextRecord::try_from(payload.clone()).unwrap();
+        assert_eq!(record.to_bytes(), payload);
+    }
 
-    pub fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        
+    #[test]
+    fn rejects_reserved_bit_set() {
+        let payload = vec![0x40];
+        assert!(TextRecord::try_from(payload).is_err());
     }
-}
\ No newline at end of file
+}
@@ -1,9 +1,54 @@
-use crate::types::Payload;
-use std::convert::{Into, TryFrom};
+use crate::error::NdefError;
+use crate::types::{Payload, WritableRecord};
+use std::convert::TryFrom;
+
+/// Abbreviation table for [`URIRecord::identifier_code`](struct.URIRecord.html#structfield.identifier_code),
+/// mapping each code to the URI prefix it stands in for
+///
+/// Kept as a single table shared by both the decoder and the encoder so the two directions can't drift apart
+const URI_PREFIX_TABLE: &[(u8, &str)] = &[
+	(0x00, ""),
+	(0x01, "http://www."),
+	(0x02, "https://www."),
+	(0x03, "http://"),
+	(0x04, "https://"),
+	(0x05, "tel:"),
+	(0x06, "mailto:"),
+	(0x07, "ftp://anonymous:anonymous@"),
+	(0x08, "ftp://ftp."),
+	(0x09, "ftps://"),
+	(0x0A, "sftp://"),
+	(0x0B, "smb://"),
+	(0x0C, "nfs://"),
+	(0x0D, "ftp://"),
+	(0x0E, "dav://"),
+	(0x0F, "news:"),
+	(0x10, "telnet://"),
+	(0x11, "imap:"),
+	(0x12, "rtsp://"),
+	(0x13, "urn:"),
+	(0x14, "pop:"),
+	(0x15, "sip:"),
+	(0x16, "sips:"),
+	(0x17, "tftp:"),
+	(0x18, "btspp://"),
+	(0x19, "btl2cap://"),
+	(0x1A, "btgoep://"),
+	(0x1B, "tcpobex://"),
+	(0x1C, "irdaobex://"),
+	(0x1D, "file://"),
+	(0x1E, "urn:epc:id:"),
+	(0x1F, "urn:epc:tag:"),
+	(0x20, "urn:epc:pat:"),
+	(0x21, "urn:epc:raw:"),
+	(0x22, "urn:epc:"),
+	(0x23, "urn:nfc:"),
+];
 
 /// Well-Known Record - URI - [TNF Record Type `0x01`](enum.TypeNameFormat.html#variant.WellKnown)
 ///
 /// Well Known Type is "U" ([Type](struct.Record.html#structfield.record_type) field will be `0x55`)
+#[derive(Debug, PartialEq)]
 pub struct URIRecord {
 	/// This field allows the uri_field to be compacted, by expressing common protocols as a 1 byte value
 	///
@@ -51,31 +96,149 @@ pub struct URIRecord {
 	pub identifier_code: u8,
 
 	/// This field must be encoded as UTF-8, unless the URI scheme specifies differently
+	///
+	/// Holds only the portion of the URI left over after stripping the prefix named by
+	/// [`identifier_code`](#structfield.identifier_code) - use [`full_uri`](#method.full_uri) to get the
+	/// complete, uncompressed URI
 	pub uri_field: String,
 }
 
+impl URIRecord {
+	/// Build a record from a complete URI, compressing it against
+	/// [`URI_PREFIX_TABLE`](constant.URI_PREFIX_TABLE.html) by picking the longest matching prefix
+	pub fn from_uri(uri: &str) -> Self {
+		let longest_match = URI_PREFIX_TABLE
+			.iter()
+			.filter(|(_, prefix)| !prefix.is_empty() && uri.starts_with(prefix))
+			.max_by_key(|(_, prefix)| prefix.len());
+
+		match longest_match {
+			Some((code, prefix)) => URIRecord {
+				identifier_code: *code,
+				uri_field: uri[prefix.len()..].to_string(),
+			},
+			None => URIRecord {
+				identifier_code: 0x00,
+				uri_field: uri.to_string(),
+			},
+		}
+	}
+
+	/// Reconstruct the complete, uncompressed URI by prepending the prefix named by
+	/// [`identifier_code`](#structfield.identifier_code) onto [`uri_field`](#structfield.uri_field)
+	pub fn full_uri(&self) -> String {
+		let prefix = URI_PREFIX_TABLE
+			.iter()
+			.find(|(code, _)| *code == self.identifier_code)
+			.map(|(_, prefix)| *prefix)
+			.unwrap_or("");
+
+		format!("{}{}", prefix, self.uri_field)
+	}
+}
+
 impl Payload<URIRecord> for URIRecord {
 	fn new(bytes: Vec<u8>) -> Self {
-		URIRecord {
+		URIRecord::try_from(bytes).unwrap_or_else(|_| URIRecord {
 			identifier_code: 0x00,
 			uri_field: "".to_string(),
-		}
+		})
 	}
 }
 
 impl TryFrom<Vec<u8>> for URIRecord {
-	type Error = String;
+	type Error = NdefError;
 
 	fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
+		if value.is_empty() {
+			return Err(NdefError::TooFewBytes { needed: 1, got: 0 });
+		}
+
+		let identifier_code = value[0];
+
+		if !URI_PREFIX_TABLE.iter().any(|(code, _)| *code == identifier_code) {
+			return Err(NdefError::ReservedUriIdentifierCode(identifier_code));
+		}
+
+		let uri_field = match std::str::from_utf8(&value[1..]) {
+			Ok(uri) => uri.to_string(),
+			Err(_) => return Err(NdefError::InvalidUtf8),
+		};
+
 		Ok(URIRecord {
-			identifier_code: 0x00,
-			uri_field: "".to_string(),
+			identifier_code,
+			uri_field,
 		})
 	}
 }
 
-impl Into<Vec<u8>> for URIRecord {
-	fn into(self) -> Vec<u8> {
-		vec![]
+impl WritableRecord for URIRecord {
+	fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = vec![self.identifier_code];
+		bytes.extend_from_slice(self.uri_field.as_bytes());
+		bytes
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Payload, URIRecord, WritableRecord};
+	use std::convert::TryFrom;
+
+	#[test]
+	fn decodes_compressed_prefix() {
+		let mut payload = vec![0x01]; // http://www.
+		payload.extend_from_slice(b"example.com");
+
+		let record = URIRecord::try_from(payload).unwrap();
+		assert_eq!(record.identifier_code, 0x01);
+		assert_eq!(record.uri_field, "example.com");
+		assert_eq!(record.full_uri(), "http://www.example.com");
+	}
+
+	#[test]
+	fn decodes_no_prefix() {
+		let mut payload = vec![0x00];
+		payload.extend_from_slice(b"example.com");
+
+		let record = URIRecord::try_from(payload).unwrap();
+		assert_eq!(record.full_uri(), "example.com");
+	}
+
+	#[test]
+	fn rejects_reserved_identifier_code() {
+		let payload = vec![0x24, 0x61];
+		assert!(URIRecord::try_from(payload).is_err());
+	}
+
+	#[test]
+	fn from_uri_picks_longest_matching_prefix() {
+		// "http://www." (0x01) and "http://" (0x03) both match, the longer one should win
+		let record = URIRecord::from_uri("http://www.example.com");
+		assert_eq!(record.identifier_code, 0x01);
+		assert_eq!(record.uri_field, "example.com");
+	}
+
+	#[test]
+	fn from_uri_falls_back_to_no_prefix() {
+		let record = URIRecord::from_uri("ldap://example.com");
+		assert_eq!(record.identifier_code, 0x00);
+		assert_eq!(record.uri_field, "ldap://example.com");
+	}
+
+	#[test]
+	fn round_trips_through_to_bytes() {
+		let record = URIRecord::from_uri("https://example.com/path");
+		let bytes = record.to_bytes();
+
+		let decoded = URIRecord::try_from(bytes).unwrap();
+		assert_eq!(decoded.full_uri(), "https://example.com/path");
+	}
+
+	#[test]
+	fn payload_new_falls_back_on_error() {
+		let record = URIRecord::new(vec![0xff]);
+		assert_eq!(record.identifier_code, 0x00);
+		assert_eq!(record.uri_field, "");
 	}
 }